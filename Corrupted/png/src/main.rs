@@ -6,12 +6,10 @@ mod png {
         network_endian::U32, Immutable, IntoBytes, KnownLayout, TryFromBytes, TryReadError,
     };
 
-    use flate2::{read::ZlibEncoder, write::ZlibDecoder, Compression, Crc};
+    use flate2::{read::ZlibEncoder, Compression};
     use std::{
         io::{self, Read, Write},
         mem::size_of,
-        ops::AddAssign,
-        slice::{from_raw_parts, from_raw_parts_mut},
     };
 
     const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
@@ -32,31 +30,39 @@ mod png {
         }
     }
 
-    impl AddAssign for Rgba {
-        fn add_assign(&mut self, rhs: Self) {
-            *self = Rgba {
-                r: self.r.wrapping_add(rhs.r),
-                g: self.g.wrapping_add(rhs.g),
-                b: self.b.wrapping_add(rhs.b),
-                a: self.a.wrapping_add(rhs.a),
-            }
-        }
-    }
-
-    // We only care about rbga parse will fail if colour type other than 
-    // True Colour with alpha is present
     #[repr(u8)]
     #[non_exhaustive]
-    #[derive(TryFromBytes, Clone, Copy, Debug, IntoBytes, Immutable)]
+    #[derive(TryFromBytes, Clone, Copy, Debug, IntoBytes, Immutable, PartialEq, Eq)]
     pub enum ColourType {
+        Grayscale = 0,
+        TrueColour = 2,
+        IndexedColour = 3,
+        GrayscaleWithAlpha = 4,
         TrueColourWithAlpha = 6,
     }
 
+    impl ColourType {
+        /// Channels per pixel as stored in the scanline, before any palette
+        /// expansion — an indexed pixel is one palette index, not a colour.
+        pub fn channels(self) -> u32 {
+            match self {
+                ColourType::Grayscale | ColourType::IndexedColour => 1,
+                ColourType::GrayscaleWithAlpha => 2,
+                ColourType::TrueColour => 3,
+                ColourType::TrueColourWithAlpha => 4,
+            }
+        }
+    }
+
     #[repr(u8)]
     #[non_exhaustive]
-    #[derive(TryFromBytes, Clone, Copy, Debug, IntoBytes, Immutable)]
+    #[derive(TryFromBytes, Clone, Copy, Debug, IntoBytes, Immutable, PartialEq, Eq)]
     pub enum BitDepth {
+        One = 1,
+        Two = 2,
+        Four = 4,
         Eight = 8,
+        Sixteen = 16,
     }
 
     #[repr(C, packed)]
@@ -71,6 +77,35 @@ mod png {
         pub interlace_method: u8,
     }
 
+    impl ImageHeader {
+        /// Bits per pixel as laid out in the scanline (before palette
+        /// expansion), i.e. channels times bit depth.
+        pub fn bits_per_pixel(&self) -> u32 {
+            self.colour_type.channels() * self.bit_depth as u8 as u32
+        }
+
+        /// The filter "bpp": byte distance filters reconstruct across,
+        /// rounded up to a whole byte and never less than one (PNG ยง6.3).
+        pub fn filter_distance(&self) -> usize {
+            (self.bits_per_pixel() as usize).div_ceil(8).max(1)
+        }
+
+        /// Length in bytes of one reconstructed scanline, not counting the
+        /// leading filter-type byte.
+        pub fn row_bytes(&self) -> usize {
+            (self.width.get() as usize * self.bits_per_pixel() as usize).div_ceil(8)
+        }
+
+        /// Number of bytes [`decode_into`] needs in its output slice to hold
+        /// this image's reconstructed pixels, expanded to [`Rgba`].
+        pub fn required_bytes(&self) -> Result<usize, ParseError> {
+            (self.width.get() as usize)
+                .checked_mul(self.height.get() as usize)
+                .and_then(|pixels| pixels.checked_mul(size_of::<Rgba>()))
+                .ok_or(ParseError::TooLargeForUsize)
+        }
+    }
+
     struct Chunk<'a> {
         pub data: &'a [u8],
         pub chunk_type: [u8; 4],
@@ -110,13 +145,47 @@ mod png {
     pub enum ParseError<'a> {
         InvalidSignature,
         ImageHeaderNotFound,
-        DecompressError(io::Error),
         ImageHeaderInvalid(TryReadError<&'a [u8], ImageHeader>),
+        UnsupportedInterlaceMethod(u8),
+        UnexpectedEof,
+        BadZlibHeader,
+        BadBlockType,
+        BadNlen,
+        BadCode,
+        BadBackReference,
+        AdlerMismatch,
+        BadCrc {
+            chunk_type: [u8; 4],
+            expected: u32,
+            found: u32,
+        },
+        TooLargeForUsize,
+        BufferTooSmall,
+        PaletteRequired,
+        PaletteIndexOutOfRange(usize),
+        BadFilterType(u8),
+    }
+
+    /// Controls how strictly [`RgbaImage::parse_with_options`] treats chunk
+    /// corruption. [`RgbaImage::parse`] uses [`Options::default`], which is strict.
+    #[derive(Clone, Copy, Debug)]
+    pub struct Options {
+        /// When `true`, a chunk whose CRC-32 doesn't match its data is a hard
+        /// [`ParseError::BadCrc`]. When `false`, the chunk is silently skipped,
+        /// except `IHDR`, which is still used as read.
+        pub verify_crc: bool,
+    }
+
+    impl Default for Options {
+        fn default() -> Self {
+            Self { verify_crc: true }
+        }
     }
 
     pub struct RgbaImage {
         buffer: Box<[u8]>,
         header: ImageHeader,
+        palette: Option<Box<[Rgba]>>,
     }
 
     #[repr(u8)]
@@ -129,96 +198,286 @@ mod png {
         Paeth = 4,
     }
 
+    const fn crc_table() -> [u32; 256] {
+        let mut table = [0u32; 256];
+        let mut n = 0;
+
+        while n < 256 {
+            let mut c = n as u32;
+            let mut k = 0;
+
+            while k < 8 {
+                c = if c & 1 != 0 { 0xEDB88320 ^ (c >> 1) } else { c >> 1 };
+                k += 1;
+            }
+
+            table[n] = c;
+            n += 1;
+        }
+
+        table
+    }
+
+    const CRC_TABLE: [u32; 256] = crc_table();
+
+    // Shared by encode (write_chunk) and decode (RgbaImage::parse_with_options)
+    // so both sides agree on a single CRC-32 implementation.
+    fn crc32(chunk_type: &[u8; 4], data: &[u8]) -> u32 {
+        !chunk_type
+            .iter()
+            .chain(data)
+            .fold(0xFFFFFFFFu32, |a, &b| (a >> 8) ^ CRC_TABLE[((a ^ b as u32) & 0xFF) as usize])
+    }
+
+    // Returns whether `chunk` should be used. A CRC mismatch is a hard error
+    // under `Options::verify_crc`, otherwise the chunk is just skipped.
+    fn verify_crc<'a>(chunk: &Chunk<'a>, options: Options) -> Result<bool, ParseError<'a>> {
+        let found = crc32(&chunk.chunk_type, chunk.data);
+
+        if found == chunk.crc {
+            return Ok(true);
+        }
+
+        if options.verify_crc {
+            Err(ParseError::BadCrc {
+                chunk_type: chunk.chunk_type,
+                expected: chunk.crc,
+                found,
+            })
+        } else {
+            Ok(false)
+        }
+    }
+
     fn write_chunk<W: Write>(writer: &mut W, name: &[u8; 4], data: &[u8]) -> io::Result<()> {
         writer.write_u32::<BigEndian>(data.len() as u32)?;
         writer.write_all(name)?;
         writer.write_all(data)?;
-
-        let mut crc = Crc::new();
-        crc.update(name);
-        crc.update(data);
-        writer.write_u32::<BigEndian>(crc.sum())?;
+        writer.write_u32::<BigEndian>(crc32(name, data))?;
 
         Ok(())
     }
 
-    impl RgbaImage {
-        pub fn parse(mut png: &[u8]) -> Result<Self, ParseError> {
-            let mut signature = [0; 8];
-            png.read_exact(&mut signature).map_err(|_| ParseError::InvalidSignature)?;
+    // Parses the signature and the leading IHDR chunk, handing back the chunk
+    // (still CRC-unchecked) and an iterator positioned right after it. Shared
+    // by the full `parse` path and the header-only `read_header` entry point.
+    fn read_header_chunk(mut png: &[u8]) -> Result<(Chunk<'_>, ChunkIterator<'_>), ParseError<'_>> {
+        let mut signature = [0; 8];
+        png.read_exact(&mut signature).map_err(|_| ParseError::InvalidSignature)?;
+
+        if signature != SIGNATURE {
+            return Err(ParseError::InvalidSignature);
+        }
+
+        let mut chunks = ChunkIterator::new(png);
+
+        let header_chunk = chunks
+            .next()
+            .filter(|c| c.chunk_type == *b"IHDR")
+            .ok_or(ParseError::ImageHeaderNotFound)?;
+
+        Ok((header_chunk, chunks))
+    }
 
-            if signature != SIGNATURE {
-                return Err(ParseError::InvalidSignature);
+    /// Parses only the signature and `IHDR`, without touching `IDAT`. Pair
+    /// with [`ImageHeader::required_bytes`] and [`decode_into`] to decode
+    /// without ever allocating the output buffer internally. Verifies the
+    /// `IHDR` CRC-32; use [`read_header_with_options`] to opt out.
+    pub fn read_header(png: &[u8]) -> Result<ImageHeader, ParseError> {
+        read_header_with_options(png, Options::default())
+    }
+
+    /// Like [`read_header`], but lets the caller control CRC-32 verification
+    /// via `options` (see [`Options::verify_crc`]), matching
+    /// [`RgbaImage::parse_with_options`].
+    pub fn read_header_with_options(png: &[u8], options: Options) -> Result<ImageHeader, ParseError> {
+        let (header_chunk, _) = read_header_chunk(png)?;
+        verify_crc(&header_chunk, options)?;
+
+        ImageHeader::try_read_from_bytes(header_chunk.data).map_err(ParseError::ImageHeaderInvalid)
+    }
+
+    /// Inflates and unfilters `png` straight into `out`, without allocating
+    /// the reconstructed pixel buffer internally. `header` must be the result
+    /// of [`read_header`] on the same data, and `out` must hold at least
+    /// `width * height` pixels ([`ImageHeader::required_bytes`] bytes),
+    /// otherwise [`ParseError::BufferTooSmall`]. Works for any colour type/bit
+    /// depth `header` reports, expanding every scanline to [`Rgba`] as it goes.
+    /// Verifies every chunk's CRC-32; use [`decode_into_with_options`] to opt
+    /// out.
+    pub fn decode_into<'a>(
+        png: &'a [u8],
+        header: &ImageHeader,
+        out: &mut [Rgba],
+    ) -> Result<(), ParseError<'a>> {
+        decode_into_with_options(png, header, Options::default(), out)
+    }
+
+    /// Like [`decode_into`], but lets the caller control CRC-32 verification
+    /// via `options` (see [`Options::verify_crc`]), matching
+    /// [`RgbaImage::parse_with_options`].
+    pub fn decode_into_with_options<'a>(
+        png: &'a [u8],
+        header: &ImageHeader,
+        options: Options,
+        out: &mut [Rgba],
+    ) -> Result<(), ParseError<'a>> {
+        let width = header.width.get() as usize;
+        let height = header.height.get() as usize;
+        let pixel_count = width.checked_mul(height).ok_or(ParseError::TooLargeForUsize)?;
+
+        if out.len() < pixel_count {
+            return Err(ParseError::BufferTooSmall);
+        }
+
+        let (header_chunk, chunks) = read_header_chunk(png)?;
+        verify_crc(&header_chunk, options)?;
+
+        let mut compressed = Vec::new();
+        let mut palette: Option<Vec<Rgba>> = None;
+
+        for chunk in chunks {
+            if !verify_crc(&chunk, options)? {
+                continue;
+            }
+
+            if chunk.chunk_type == *b"PLTE" {
+                palette = Some(expand::read_palette(chunk.data));
+            } else if chunk.chunk_type == *b"tRNS" {
+                if let Some(palette) = &mut palette {
+                    expand::apply_trns(palette, chunk.data);
+                }
+            } else if chunk.chunk_type == *b"IDAT" {
+                compressed.extend_from_slice(chunk.data);
             }
+        }
 
-            let mut chunks = ChunkIterator::new(png);
+        let decompressed = inflate::zlib_decompress(&compressed)?;
+        let pixels = &mut out[..pixel_count];
 
-            let header_chunk = chunks
-                .next()
-                .filter(|c| c.chunk_type == *b"IHDR")
-                .ok_or(ParseError::ImageHeaderNotFound)?;
+        match header.interlace_method {
+            0 => unfilter_rows(&decompressed, header, palette.as_deref(), pixels)?,
+            1 => adam7::deinterlace_into(&decompressed, header, palette.as_deref(), pixels)?,
+            method => return Err(ParseError::UnsupportedInterlaceMethod(method)),
+        }
+
+        Ok(())
+    }
+
+    // Reconstructs each scanline of a non-interlaced image and expands it
+    // straight to `Rgba`, without ever materialising a native-format buffer.
+    fn unfilter_rows(
+        data: &[u8],
+        header: &ImageHeader,
+        palette: Option<&[Rgba]>,
+        out: &mut [Rgba],
+    ) -> Result<(), ParseError<'static>> {
+        let width = header.width.get() as usize;
+        let row_bytes = header.row_bytes();
+        let bpp = header.filter_distance();
+        let stride = row_bytes + size_of::<FilterType>();
+        let mut above = vec![0u8; row_bytes];
+        let mut cursor = data;
+
+        for out_row in out.chunks_exact_mut(width) {
+            let line = cursor.get(..stride).ok_or(ParseError::UnexpectedEof)?;
+            cursor = &cursor[stride..];
+
+            let (filter, pixel_bytes) =
+                FilterType::try_ref_from_prefix(line).map_err(|_| ParseError::BadFilterType(line[0]))?;
+            let mut current = pixel_bytes.to_vec();
+
+            match filter {
+                FilterType::Up => recon::up(&mut current, &above),
+                FilterType::Paeth => recon::paeth(&mut current, &above, bpp),
+                FilterType::Average => recon::average(&mut current, &above, bpp),
+                FilterType::Sub => recon::sub(&mut current, bpp),
+                FilterType::NoFilter => {}
+            }
+
+            expand::row(header, &current, palette, out_row)?;
+            above.copy_from_slice(&current);
+        }
+
+        Ok(())
+    }
+
+    impl RgbaImage {
+        pub fn parse(png: &[u8]) -> Result<Self, ParseError> {
+            Self::parse_with_options(png, Options::default())
+        }
+
+        pub fn parse_with_options(png: &[u8], options: Options) -> Result<Self, ParseError> {
+            let (header_chunk, chunks) = read_header_chunk(png)?;
+
+            verify_crc(&header_chunk, options)?;
 
             let header = ImageHeader::try_read_from_bytes(header_chunk.data)
                 .map_err(ParseError::ImageHeaderInvalid)?;
 
-            let mut writer = Vec::new();
-            let mut z = ZlibDecoder::new(writer);
+            let mut compressed = Vec::new();
+            let mut palette: Option<Vec<Rgba>> = None;
 
             for chunk in chunks {
-                if chunk.chunk_type == *b"IDAT" {
-                    z.write_all(chunk.data)
-                        .map_err(ParseError::DecompressError)?;
+                if !verify_crc(&chunk, options)? {
+                    continue;
+                }
+
+                if chunk.chunk_type == *b"PLTE" {
+                    palette = Some(expand::read_palette(chunk.data));
+                } else if chunk.chunk_type == *b"tRNS" {
+                    if let Some(palette) = &mut palette {
+                        expand::apply_trns(palette, chunk.data);
+                    }
+                } else if chunk.chunk_type == *b"IDAT" {
+                    compressed.extend_from_slice(chunk.data);
                 }
             }
 
-            writer = z.finish().map_err(ParseError::DecompressError)?;
+            let decompressed = inflate::zlib_decompress(&compressed)?;
+
+            let buffer = match header.interlace_method {
+                0 => decompressed.into(),
+                1 => adam7::deinterlace_rows(&decompressed, &header)?,
+                method => return Err(ParseError::UnsupportedInterlaceMethod(method)),
+            };
 
             Ok(Self {
                 header,
-                buffer: writer.into(),
+                buffer,
+                palette: palette.map(Vec::into_boxed_slice),
             })
         }
 
-        pub fn lines(&self) -> impl Iterator<Item = (&FilterType, &[Rgba])> {
-            let length = (self.header.width.get() as usize * size_of::<Rgba>()) + size_of::<FilterType>();
+        /// Raw, native-format (colour type/bit depth as declared by the
+        /// header) scanlines, each still led by its [`FilterType`] byte.
+        pub fn lines(&self) -> impl Iterator<Item = (&FilterType, &[u8])> {
+            let length = self.header.row_bytes() + size_of::<FilterType>();
 
-            self.buffer.chunks_exact(length).map(|r| {
-                let (filter, pixels) = FilterType::try_ref_from_prefix(r).unwrap();
-                let pixels = unsafe {
-                    from_raw_parts(
-                        pixels.as_ptr().cast::<Rgba>(),
-                        self.header.width.get() as usize,
-                    )
-                };
-                (filter, pixels)
-            })
+            self.buffer
+                .chunks_exact(length)
+                .map(|r| FilterType::try_ref_from_prefix(r).unwrap())
         }
 
-        pub fn lines_mut(&mut self) -> impl Iterator<Item = (&mut FilterType, &mut [Rgba])> {
-            let length = (self.header.width.get() as usize * size_of::<Rgba>()) + size_of::<FilterType>();
+        pub fn lines_mut(&mut self) -> impl Iterator<Item = (&mut FilterType, &mut [u8])> {
+            let length = self.header.row_bytes() + size_of::<FilterType>();
 
-            self.buffer.chunks_exact_mut(length).map(|r| {
-                let (filter, pixels) = FilterType::try_mut_from_prefix(r).unwrap();
-                let pixels = unsafe {
-                    from_raw_parts_mut(
-                        pixels.as_mut_ptr().cast::<Rgba>(),
-                        self.header.width.get() as usize,
-                    )
-                };
-                (filter, pixels)
-            })
+            self.buffer
+                .chunks_exact_mut(length)
+                .map(|r| FilterType::try_mut_from_prefix(r).unwrap())
         }
 
         pub fn recon(&mut self) {
             // Unfilters each scanline
-            let mut above = vec![Rgba::default(); self.header.width.get() as usize]; // Initial row
+            let bpp = self.header.filter_distance();
+            let mut above = vec![0u8; self.header.row_bytes()]; // Initial row
 
             for (filter, current) in self.lines_mut() {
                 match filter {
-                    FilterType::Up => recon::paeth(current, &above),
-                    FilterType::Paeth => recon::paeth(current, &above),
-                    FilterType::Average => recon::average(current, &above),
-                    FilterType::Sub => recon::sub(current),
+                    FilterType::Up => recon::up(current, &above),
+                    FilterType::Paeth => recon::paeth(current, &above, bpp),
+                    FilterType::Average => recon::average(current, &above, bpp),
+                    FilterType::Sub => recon::sub(current, bpp),
                     FilterType::NoFilter => {}
                 }
 
@@ -227,6 +486,23 @@ mod png {
             }
         }
 
+        /// Expands every scanline to [`Rgba`] regardless of the source colour
+        /// type/bit depth, so existing callers that only ever dealt with
+        /// [`Rgba`] keep working. Assumes the image has already been
+        /// reconstructed via [`RgbaImage::recon`].
+        pub fn to_rgba(&self) -> Result<Vec<Rgba>, ParseError> {
+            let width = self.header.width.get() as usize;
+            let mut out = Vec::with_capacity(width * self.header.height.get() as usize);
+
+            for (_, row) in self.lines() {
+                let start = out.len();
+                out.resize(start + width, Rgba::default());
+                expand::row(&self.header, row, self.palette.as_deref(), &mut out[start..])?;
+            }
+
+            Ok(out)
+        }
+
         pub fn encode<W: Write>(&self, mut writer: W) -> io::Result<()> {
             // Write png signature
             writer.write_all(&SIGNATURE)?;
@@ -241,11 +517,1199 @@ mod png {
             // Write end chunk
             write_chunk(&mut writer, b"IEND", &[])
         }
+
+        /// Like [`RgbaImage::encode`], but picks the filter type per scanline
+        /// using the minimum-sum-of-absolute-differences heuristic instead of
+        /// keeping whatever filter the scanline currently carries.
+        pub fn encode_adaptive<W: Write>(&self, mut writer: W) -> io::Result<()> {
+            writer.write_all(&SIGNATURE)?;
+            write_chunk(&mut writer, b"IHDR", self.header.as_bytes())?;
+
+            let filtered = filter::adaptive_filter(self);
+
+            let mut compressed = Vec::new();
+            ZlibEncoder::new(&filtered[..], Compression::fast()).read_to_end(&mut compressed)?;
+
+            write_chunk(&mut writer, b"IDAT", &compressed)?;
+            write_chunk(&mut writer, b"IEND", &[])
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const IHDR_DATA: [u8; 13] = [0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0];
+
+        // Signature + a single IHDR chunk, nothing else - enough for
+        // `read_header`/`read_header_with_options`, which never look past it.
+        fn minimal_png(ihdr_data: &[u8]) -> Vec<u8> {
+            let mut out = Vec::new();
+            out.extend_from_slice(&SIGNATURE);
+            write_chunk(&mut out, b"IHDR", ihdr_data).unwrap();
+            out
+        }
+
+        fn flip_crc_byte(png: &mut [u8]) {
+            let last = png.len() - 1;
+            png[last] ^= 0xFF;
+        }
+
+        #[test]
+        fn crc32_matches_known_value() {
+            assert_eq!(crc32(b"IHDR", &IHDR_DATA), 0x3a7e9b55);
+        }
+
+        #[test]
+        fn read_header_accepts_intact_crc() {
+            let png = minimal_png(&IHDR_DATA);
+            assert!(read_header(&png).is_ok());
+        }
+
+        #[test]
+        fn read_header_rejects_corrupted_crc_by_default() {
+            let mut png = minimal_png(&IHDR_DATA);
+            flip_crc_byte(&mut png);
+
+            assert!(matches!(
+                read_header(&png),
+                Err(ParseError::BadCrc { chunk_type, .. }) if chunk_type == *b"IHDR"
+            ));
+        }
+
+        #[test]
+        fn read_header_with_options_can_skip_crc_verification() {
+            let mut png = minimal_png(&IHDR_DATA);
+            flip_crc_byte(&mut png);
+
+            let options = Options { verify_crc: false };
+            assert!(read_header_with_options(&png, options).is_ok());
+        }
+    }
+
+    mod filter {
+        // Forward (encode-side) counterparts of `recon`'s unfilter functions,
+        // used to try every filter type per scanline and keep the cheapest
+        // one. Works at the raw byte/bpp granularity so it's agnostic to the
+        // image's colour type and bit depth.
+        use super::{recon::paeth_predictor, FilterType, RgbaImage};
+
+        fn sub(line: &[u8], bpp: usize) -> Vec<u8> {
+            line.iter()
+                .enumerate()
+                .map(|(i, &byte)| byte.wrapping_sub(if i >= bpp { line[i - bpp] } else { 0 }))
+                .collect()
+        }
+
+        fn up(line: &[u8], above: &[u8]) -> Vec<u8> {
+            line.iter()
+                .zip(above)
+                .map(|(&byte, &above_byte)| byte.wrapping_sub(above_byte))
+                .collect()
+        }
+
+        fn average(line: &[u8], above: &[u8], bpp: usize) -> Vec<u8> {
+            line.iter()
+                .zip(above)
+                .enumerate()
+                .map(|(i, (&byte, &above_byte))| {
+                    let left = if i >= bpp { line[i - bpp] as u16 } else { 0 };
+                    byte.wrapping_sub(((left + above_byte as u16) >> 1) as u8)
+                })
+                .collect()
+        }
+
+        fn paeth(line: &[u8], above: &[u8], bpp: usize) -> Vec<u8> {
+            line.iter()
+                .zip(above)
+                .enumerate()
+                .map(|(i, (&byte, &above_byte))| {
+                    let left = if i >= bpp { line[i - bpp] } else { 0 };
+                    let upper_left = if i >= bpp { above[i - bpp] } else { 0 };
+                    byte.wrapping_sub(paeth_predictor(left, above_byte, upper_left))
+                })
+                .collect()
+        }
+
+        // libpng's minimum-sum-of-absolute-differences heuristic: treat each
+        // filtered byte as signed and sum the magnitudes.
+        fn score(line: &[u8]) -> u32 {
+            line.iter()
+                .map(|&byte| {
+                    let byte = byte as u32;
+                    byte.min(256 - byte)
+                })
+                .sum()
+        }
+
+        pub fn adaptive_filter(image: &RgbaImage) -> Vec<u8> {
+            let row_bytes = image.header.row_bytes();
+            let bpp = image.header.filter_distance();
+            let mut above = vec![0u8; row_bytes];
+            let mut out = Vec::with_capacity(image.buffer.len());
+
+            for (current_filter, current) in image.lines() {
+                let mut raw = current.to_vec();
+                match current_filter {
+                    FilterType::Up => super::recon::up(&mut raw, &above),
+                    FilterType::Paeth => super::recon::paeth(&mut raw, &above, bpp),
+                    FilterType::Average => super::recon::average(&mut raw, &above, bpp),
+                    FilterType::Sub => super::recon::sub(&mut raw, bpp),
+                    FilterType::NoFilter => {}
+                }
+
+                let candidates = [
+                    (FilterType::NoFilter, raw.clone()),
+                    (FilterType::Sub, sub(&raw, bpp)),
+                    (FilterType::Up, up(&raw, &above)),
+                    (FilterType::Average, average(&raw, &above, bpp)),
+                    (FilterType::Paeth, paeth(&raw, &above, bpp)),
+                ];
+
+                let (best_filter, best_line) = candidates
+                    .into_iter()
+                    .min_by_key(|(_, line)| score(line))
+                    .unwrap();
+
+                out.push(best_filter as u8);
+                out.extend(best_line);
+
+                above = raw;
+            }
+
+            out
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use super::super::{ImageHeader, U32};
+
+            fn header(width: u32, height: u32) -> ImageHeader {
+                ImageHeader {
+                    width: U32::new(width),
+                    height: U32::new(height),
+                    bit_depth: super::super::BitDepth::Eight,
+                    colour_type: super::super::ColourType::Grayscale,
+                    compression_method: 0,
+                    filter_method: 0,
+                    interlace_method: 0,
+                }
+            }
+
+            #[test]
+            fn ramp_scores_lower_under_sub_than_no_filter() {
+                // A rising ramp turns into a constant-offset line under Sub,
+                // which the MSAD heuristic always prefers over the raw bytes.
+                let raw = [10u8, 20, 30, 40];
+                assert!(score(&sub(&raw, 1)) < score(&raw));
+            }
+
+            #[test]
+            fn adaptive_filter_picks_the_cheapest_filter_per_row() {
+                // Two rows stored as NoFilter (filter byte 0): a rising ramp,
+                // for which Sub is cheapest, and an all-zero row, for which
+                // NoFilter is already optimal (it and Sub both score 0, and
+                // ties go to the first candidate).
+                let image = RgbaImage {
+                    header: header(4, 2),
+                    buffer: vec![0, 10, 20, 30, 40, 0, 0, 0, 0, 0].into_boxed_slice(),
+                    palette: None,
+                };
+
+                let filtered = adaptive_filter(&image);
+                let stride = 1 + 4;
+
+                assert_eq!(filtered[0], FilterType::Sub as u8);
+                assert_eq!(&filtered[1..stride], &sub(&[10, 20, 30, 40], 1));
+                assert_eq!(filtered[stride], FilterType::NoFilter as u8);
+                assert_eq!(&filtered[stride + 1..], &[0, 0, 0, 0]);
+            }
+        }
+    }
+
+    mod expand {
+        // Expands reconstructed (already-unfiltered), native-format
+        // scanlines into `Rgba`, the one pixel type every caller of
+        // `RgbaImage`/`decode_into` deals with regardless of the source's
+        // colour type or bit depth.
+        use super::{BitDepth, ColourType, ImageHeader, ParseError, Rgba};
+
+        // Reads the `index`-th sample (one colour/alpha/index channel, in
+        // `bit_depth`-sized units) out of a reconstructed scanline, at full
+        // native precision.
+        fn read_sample(row: &[u8], bit_depth: BitDepth, index: usize) -> u16 {
+            let bit_depth = bit_depth as u8;
+
+            if bit_depth >= 8 {
+                let bytes = bit_depth as usize / 8;
+                let offset = index * bytes;
+
+                if bytes == 1 {
+                    row[offset] as u16
+                } else {
+                    u16::from_be_bytes([row[offset], row[offset + 1]])
+                }
+            } else {
+                let samples_per_byte = 8 / bit_depth as usize;
+                let byte = row[index / samples_per_byte];
+                let shift = 8 - bit_depth as usize * (index % samples_per_byte + 1);
+                let mask = (1u16 << bit_depth) - 1;
+
+                (byte as u16 >> shift) & mask
+            }
+        }
+
+        // Writes a native-precision sample into a scanline, the inverse of
+        // `read_sample`. Used to scatter Adam7 passes into a full-width
+        // native-format buffer.
+        fn write_sample(row: &mut [u8], bit_depth: BitDepth, index: usize, value: u16) {
+            let bit_depth = bit_depth as u8;
+
+            if bit_depth >= 8 {
+                let bytes = bit_depth as usize / 8;
+                let offset = index * bytes;
+
+                if bytes == 1 {
+                    row[offset] = value as u8;
+                } else {
+                    let [hi, lo] = value.to_be_bytes();
+                    row[offset] = hi;
+                    row[offset + 1] = lo;
+                }
+            } else {
+                let samples_per_byte = 8 / bit_depth as usize;
+                let shift = 8 - bit_depth as usize * (index % samples_per_byte + 1);
+                let mask = (1u16 << bit_depth) - 1;
+                let byte = &mut row[index / samples_per_byte];
+
+                *byte = (*byte & !((mask as u8) << shift)) | ((value as u8 & mask as u8) << shift);
+            }
+        }
+
+        // Scales a sample of `bit_depth` bits to the 8-bit range: sub-byte
+        // depths by `value * 255 / max`, 16-bit samples by keeping the high byte.
+        fn scale_to_8(value: u16, bit_depth: BitDepth) -> u8 {
+            match bit_depth {
+                BitDepth::One => {
+                    if value != 0 {
+                        255
+                    } else {
+                        0
+                    }
+                }
+                BitDepth::Two => (value * 255 / 3) as u8,
+                BitDepth::Four => (value * 255 / 15) as u8,
+                BitDepth::Eight => value as u8,
+                BitDepth::Sixteen => (value >> 8) as u8,
+            }
+        }
+
+        /// Parses a `PLTE` chunk into an opaque RGB palette; pair with
+        /// [`apply_trns`] to fill in per-entry alpha from a `tRNS` chunk.
+        pub(super) fn read_palette(data: &[u8]) -> Vec<Rgba> {
+            data.chunks_exact(3)
+                .map(|rgb| Rgba::new(rgb[0], rgb[1], rgb[2], 255))
+                .collect()
+        }
+
+        /// Applies a `tRNS` chunk's per-index alpha values onto a palette
+        /// built by [`read_palette`].
+        pub(super) fn apply_trns(palette: &mut [Rgba], data: &[u8]) {
+            for (entry, &alpha) in palette.iter_mut().zip(data) {
+                entry.a = alpha;
+            }
+        }
+
+        /// Copies pixel `src_x` of `src_row` to pixel `dst_x` of `dst_row`,
+        /// both in `header`'s native (pre-expansion) pixel format. Used to
+        /// scatter Adam7 passes into a full-width native-format buffer.
+        pub(super) fn copy_pixel(
+            header: &ImageHeader,
+            src_row: &[u8],
+            src_x: usize,
+            dst_row: &mut [u8],
+            dst_x: usize,
+        ) {
+            let channels = header.colour_type.channels() as usize;
+
+            for c in 0..channels {
+                let value = read_sample(src_row, header.bit_depth, src_x * channels + c);
+                write_sample(dst_row, header.bit_depth, dst_x * channels + c, value);
+            }
+        }
+
+        /// Expands pixel `x` of a reconstructed, native-format scanline into
+        /// [`Rgba`], looking up `palette` for indexed images.
+        pub(super) fn pixel(
+            header: &ImageHeader,
+            row: &[u8],
+            palette: Option<&[Rgba]>,
+            x: usize,
+        ) -> Result<Rgba, ParseError<'static>> {
+            let channels = header.colour_type.channels() as usize;
+            let sample = |c: usize| read_sample(row, header.bit_depth, x * channels + c);
+
+            Ok(match header.colour_type {
+                ColourType::Grayscale => {
+                    let v = scale_to_8(sample(0), header.bit_depth);
+                    Rgba::new(v, v, v, 255)
+                }
+                ColourType::IndexedColour => {
+                    let index = sample(0) as usize;
+                    return palette
+                        .ok_or(ParseError::PaletteRequired)?
+                        .get(index)
+                        .copied()
+                        .ok_or(ParseError::PaletteIndexOutOfRange(index));
+                }
+                ColourType::GrayscaleWithAlpha => {
+                    let v = scale_to_8(sample(0), header.bit_depth);
+                    Rgba::new(v, v, v, scale_to_8(sample(1), header.bit_depth))
+                }
+                ColourType::TrueColour => Rgba::new(
+                    scale_to_8(sample(0), header.bit_depth),
+                    scale_to_8(sample(1), header.bit_depth),
+                    scale_to_8(sample(2), header.bit_depth),
+                    255,
+                ),
+                ColourType::TrueColourWithAlpha => Rgba::new(
+                    scale_to_8(sample(0), header.bit_depth),
+                    scale_to_8(sample(1), header.bit_depth),
+                    scale_to_8(sample(2), header.bit_depth),
+                    scale_to_8(sample(3), header.bit_depth),
+                ),
+            })
+        }
+
+        /// Expands a reconstructed, native-format scanline into `out_row`,
+        /// one [`Rgba`] per pixel.
+        pub(super) fn row(
+            header: &ImageHeader,
+            raw_row: &[u8],
+            palette: Option<&[Rgba]>,
+            out_row: &mut [Rgba],
+        ) -> Result<(), ParseError<'static>> {
+            for (x, out_pixel) in out_row.iter_mut().enumerate() {
+                *out_pixel = pixel(header, raw_row, palette, x)?;
+            }
+
+            Ok(())
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use super::super::U32;
+
+            fn header(colour_type: ColourType, bit_depth: BitDepth) -> ImageHeader {
+                ImageHeader {
+                    width: U32::new(1),
+                    height: U32::new(1),
+                    bit_depth,
+                    colour_type,
+                    compression_method: 0,
+                    filter_method: 0,
+                    interlace_method: 0,
+                }
+            }
+
+            #[test]
+            fn grayscale_scales_sub_byte_depths_to_full_range() {
+                let h = header(ColourType::Grayscale, BitDepth::One);
+                assert_eq!(pixel(&h, &[0b1000_0000], None, 0).unwrap(), Rgba::new(255, 255, 255, 255));
+                assert_eq!(pixel(&h, &[0b0000_0000], None, 0).unwrap(), Rgba::new(0, 0, 0, 255));
+
+                let h = header(ColourType::Grayscale, BitDepth::Two);
+                // Sample value 2 of 3 max -> 2*255/3 = 170.
+                assert_eq!(pixel(&h, &[0b10_000000], None, 0).unwrap(), Rgba::new(170, 170, 170, 255));
+
+                let h = header(ColourType::Grayscale, BitDepth::Four);
+                // Sample value 10 of 15 max -> 10*255/15 = 170.
+                assert_eq!(pixel(&h, &[0b1010_0000], None, 0).unwrap(), Rgba::new(170, 170, 170, 255));
+
+                let h = header(ColourType::Grayscale, BitDepth::Eight);
+                assert_eq!(pixel(&h, &[42], None, 0).unwrap(), Rgba::new(42, 42, 42, 255));
+            }
+
+            #[test]
+            fn truecolour_with_alpha_16_bit_keeps_high_byte() {
+                let h = header(ColourType::TrueColourWithAlpha, BitDepth::Sixteen);
+                let row = [0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0];
+                assert_eq!(pixel(&h, &row, None, 0).unwrap(), Rgba::new(0x12, 0x56, 0x9a, 0xde));
+            }
+
+            #[test]
+            fn grayscale_with_alpha_scales_each_channel_independently() {
+                let h = header(ColourType::GrayscaleWithAlpha, BitDepth::Eight);
+                assert_eq!(pixel(&h, &[10, 200], None, 0).unwrap(), Rgba::new(10, 10, 10, 200));
+            }
+
+            #[test]
+            fn indexed_colour_looks_up_palette_and_trns_alpha() {
+                let h = header(ColourType::IndexedColour, BitDepth::Eight);
+                let mut palette = read_palette(&[10, 20, 30, 40, 50, 60]);
+                apply_trns(&mut palette, &[128]);
+
+                assert_eq!(pixel(&h, &[0], Some(&palette), 0).unwrap(), Rgba::new(10, 20, 30, 128));
+                // Second entry has no tRNS override, so it stays fully opaque.
+                assert_eq!(pixel(&h, &[1], Some(&palette), 0).unwrap(), Rgba::new(40, 50, 60, 255));
+            }
+
+            #[test]
+            fn indexed_colour_without_palette_is_an_error() {
+                let h = header(ColourType::IndexedColour, BitDepth::Eight);
+                assert!(matches!(pixel(&h, &[0], None, 0), Err(ParseError::PaletteRequired)));
+            }
+
+            #[test]
+            fn indexed_colour_out_of_range_is_an_error() {
+                let h = header(ColourType::IndexedColour, BitDepth::Eight);
+                let palette = read_palette(&[10, 20, 30]);
+                assert!(matches!(
+                    pixel(&h, &[5], Some(&palette), 0),
+                    Err(ParseError::PaletteIndexOutOfRange(5))
+                ));
+            }
+
+            #[test]
+            fn row_expands_every_pixel() {
+                let h = ImageHeader {
+                    width: U32::new(2),
+                    height: U32::new(1),
+                    bit_depth: BitDepth::Eight,
+                    colour_type: ColourType::TrueColour,
+                    compression_method: 0,
+                    filter_method: 0,
+                    interlace_method: 0,
+                };
+                let raw = [1, 2, 3, 4, 5, 6];
+                let mut out = [Rgba::default(); 2];
+                row(&h, &raw, None, &mut out).unwrap();
+                assert_eq!(out, [Rgba::new(1, 2, 3, 255), Rgba::new(4, 5, 6, 255)]);
+            }
+        }
+    }
+
+    mod inflate {
+        // A small, dependency-free zlib/DEFLATE (RFC 1950/1951) inflater, so the
+        // IDAT stream can be decompressed without pulling in flate2/miniz.
+        use super::ParseError;
+
+        const MAX_BITS: usize = 15;
+
+        const CL_ORDER: [usize; 19] = [
+            16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+        ];
+
+        const LENGTH_BASE: [u16; 29] = [
+            3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99,
+            115, 131, 163, 195, 227, 258,
+        ];
+        const LENGTH_EXTRA: [u8; 29] = [
+            0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+        ];
+
+        const DIST_BASE: [u16; 30] = [
+            1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025,
+            1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+        ];
+        const DIST_EXTRA: [u8; 30] = [
+            0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12,
+            12, 13, 13,
+        ];
+
+        struct BitReader<'a> {
+            data: &'a [u8],
+            byte_pos: usize,
+            bit_pos: u32,
+        }
+
+        impl<'a> BitReader<'a> {
+            fn new(data: &'a [u8]) -> Self {
+                Self {
+                    data,
+                    byte_pos: 0,
+                    bit_pos: 0,
+                }
+            }
+
+            fn read_bit(&mut self) -> Result<u32, ParseError<'static>> {
+                let byte = *self
+                    .data
+                    .get(self.byte_pos)
+                    .ok_or(ParseError::UnexpectedEof)?;
+                let bit = (byte >> self.bit_pos) & 1;
+
+                self.bit_pos += 1;
+                if self.bit_pos == 8 {
+                    self.bit_pos = 0;
+                    self.byte_pos += 1;
+                }
+
+                Ok(bit as u32)
+            }
+
+            fn read_bits(&mut self, count: u32) -> Result<u32, ParseError<'static>> {
+                let mut value = 0;
+                for i in 0..count {
+                    value |= self.read_bit()? << i;
+                }
+                Ok(value)
+            }
+
+            fn align_to_byte(&mut self) {
+                if self.bit_pos != 0 {
+                    self.bit_pos = 0;
+                    self.byte_pos += 1;
+                }
+            }
+
+            fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], ParseError<'static>> {
+                self.align_to_byte();
+                let bytes = self
+                    .data
+                    .get(self.byte_pos..self.byte_pos + count)
+                    .ok_or(ParseError::UnexpectedEof)?;
+                self.byte_pos += count;
+                Ok(bytes)
+            }
+
+            fn read_u16_le(&mut self) -> Result<u16, ParseError<'static>> {
+                let bytes = self.read_bytes(2)?;
+                Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+            }
+        }
+
+        // Canonical Huffman decode table, built from a list of per-symbol code
+        // lengths as specified by RFC 1951 section 3.2.2.
+        struct HuffmanTable {
+            counts: [u16; MAX_BITS + 1],
+            symbols: Vec<u16>,
+        }
+
+        impl HuffmanTable {
+            fn build(lengths: &[u8]) -> Self {
+                let mut counts = [0u16; MAX_BITS + 1];
+                for &len in lengths {
+                    counts[len as usize] += 1;
+                }
+
+                let mut offsets = [0u16; MAX_BITS + 1];
+                for len in 1..MAX_BITS {
+                    offsets[len + 1] = offsets[len] + counts[len];
+                }
+
+                let total: u16 = counts[1..].iter().sum();
+                let mut symbols = vec![0u16; total as usize];
+                let mut next = offsets;
+
+                for (symbol, &len) in lengths.iter().enumerate() {
+                    if len != 0 {
+                        symbols[next[len as usize] as usize] = symbol as u16;
+                        next[len as usize] += 1;
+                    }
+                }
+
+                Self { counts, symbols }
+            }
+
+            fn decode(&self, reader: &mut BitReader) -> Result<u16, ParseError<'static>> {
+                let mut code = 0i32;
+                let mut first = 0i32;
+                let mut index = 0i32;
+
+                for len in 1..=MAX_BITS {
+                    code |= reader.read_bit()? as i32;
+                    let count = self.counts[len] as i32;
+
+                    if code - first < count {
+                        return Ok(self.symbols[(index + (code - first)) as usize]);
+                    }
+
+                    index += count;
+                    first += count;
+                    first <<= 1;
+                    code <<= 1;
+                }
+
+                Err(ParseError::BadCode)
+            }
+        }
+
+        fn fixed_literal_lengths() -> [u8; 288] {
+            let mut lengths = [0u8; 288];
+            lengths[0..144].fill(8);
+            lengths[144..256].fill(9);
+            lengths[256..280].fill(7);
+            lengths[280..288].fill(8);
+            lengths
+        }
+
+        fn fixed_distance_lengths() -> [u8; 30] {
+            [5; 30]
+        }
+
+        fn inflate_stored(reader: &mut BitReader, out: &mut Vec<u8>) -> Result<(), ParseError<'static>> {
+            reader.align_to_byte();
+            let len = reader.read_u16_le()?;
+            let nlen = reader.read_u16_le()?;
+
+            if len != !nlen {
+                return Err(ParseError::BadNlen);
+            }
+
+            out.extend_from_slice(reader.read_bytes(len as usize)?);
+            Ok(())
+        }
+
+        fn decode_block(
+            reader: &mut BitReader,
+            out: &mut Vec<u8>,
+            lit_table: &HuffmanTable,
+            dist_table: &HuffmanTable,
+        ) -> Result<(), ParseError<'static>> {
+            loop {
+                match lit_table.decode(reader)? {
+                    symbol @ 0..=255 => out.push(symbol as u8),
+                    256 => return Ok(()),
+                    symbol @ 257..=285 => {
+                        let index = (symbol - 257) as usize;
+                        let length = LENGTH_BASE[index] as usize
+                            + reader.read_bits(LENGTH_EXTRA[index] as u32)? as usize;
+
+                        let dist_symbol = dist_table.decode(reader)? as usize;
+                        let distance = DIST_BASE
+                            .get(dist_symbol)
+                            .ok_or(ParseError::BadCode)?
+                            .checked_add(
+                                reader.read_bits(
+                                    *DIST_EXTRA.get(dist_symbol).ok_or(ParseError::BadCode)? as u32,
+                                )? as u16,
+                            )
+                            .ok_or(ParseError::BadBackReference)? as usize;
+
+                        if distance == 0 || distance > out.len() {
+                            return Err(ParseError::BadBackReference);
+                        }
+
+                        let start = out.len() - distance;
+                        for i in 0..length {
+                            out.push(out[start + i]);
+                        }
+                    }
+                    _ => return Err(ParseError::BadCode),
+                }
+            }
+        }
+
+        fn inflate_fixed(reader: &mut BitReader, out: &mut Vec<u8>) -> Result<(), ParseError<'static>> {
+            let lit_table = HuffmanTable::build(&fixed_literal_lengths());
+            let dist_table = HuffmanTable::build(&fixed_distance_lengths());
+            decode_block(reader, out, &lit_table, &dist_table)
+        }
+
+        fn read_dynamic_lengths(
+            reader: &mut BitReader,
+            cl_table: &HuffmanTable,
+            total: usize,
+        ) -> Result<Vec<u8>, ParseError<'static>> {
+            let mut lengths = Vec::with_capacity(total);
+
+            while lengths.len() < total {
+                match cl_table.decode(reader)? {
+                    symbol @ 0..=15 => lengths.push(symbol as u8),
+                    16 => {
+                        let repeat = 3 + reader.read_bits(2)?;
+                        let &prev = lengths.last().ok_or(ParseError::BadCode)?;
+                        lengths.resize(lengths.len() + repeat as usize, prev);
+                    }
+                    17 => {
+                        let repeat = 3 + reader.read_bits(3)?;
+                        lengths.resize(lengths.len() + repeat as usize, 0);
+                    }
+                    18 => {
+                        let repeat = 11 + reader.read_bits(7)?;
+                        lengths.resize(lengths.len() + repeat as usize, 0);
+                    }
+                    _ => return Err(ParseError::BadCode),
+                }
+            }
+
+            if lengths.len() != total {
+                return Err(ParseError::BadCode);
+            }
+
+            Ok(lengths)
+        }
+
+        fn inflate_dynamic(reader: &mut BitReader, out: &mut Vec<u8>) -> Result<(), ParseError<'static>> {
+            let hlit = reader.read_bits(5)? as usize + 257;
+            let hdist = reader.read_bits(5)? as usize + 1;
+            let hclen = reader.read_bits(4)? as usize + 4;
+
+            let mut cl_lengths = [0u8; 19];
+            for &position in &CL_ORDER[..hclen] {
+                cl_lengths[position] = reader.read_bits(3)? as u8;
+            }
+
+            let cl_table = HuffmanTable::build(&cl_lengths);
+            let lengths = read_dynamic_lengths(reader, &cl_table, hlit + hdist)?;
+
+            let lit_table = HuffmanTable::build(&lengths[..hlit]);
+            let dist_table = HuffmanTable::build(&lengths[hlit..]);
+
+            decode_block(reader, out, &lit_table, &dist_table)
+        }
+
+        fn inflate(data: &[u8]) -> Result<Vec<u8>, ParseError<'static>> {
+            let mut reader = BitReader::new(data);
+            let mut out = Vec::new();
+
+            loop {
+                let is_final = reader.read_bit()? == 1;
+
+                match reader.read_bits(2)? {
+                    0 => inflate_stored(&mut reader, &mut out)?,
+                    1 => inflate_fixed(&mut reader, &mut out)?,
+                    2 => inflate_dynamic(&mut reader, &mut out)?,
+                    _ => return Err(ParseError::BadBlockType),
+                }
+
+                if is_final {
+                    break;
+                }
+            }
+
+            Ok(out)
+        }
+
+        fn adler32(data: &[u8]) -> u32 {
+            const MOD_ADLER: u32 = 65521;
+
+            let mut a: u32 = 1;
+            let mut b: u32 = 0;
+
+            for &byte in data {
+                a = (a + byte as u32) % MOD_ADLER;
+                b = (b + a) % MOD_ADLER;
+            }
+
+            (b << 16) | a
+        }
+
+        /// Decompresses a zlib stream (RFC 1950 header + DEFLATE payload + Adler-32
+        /// trailer) as used by the PNG `IDAT` chunks.
+        pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, ParseError<'static>> {
+            if data.len() < 6 {
+                return Err(ParseError::UnexpectedEof);
+            }
+
+            let cmf = data[0];
+            let flg = data[1];
+
+            if cmf & 0x0F != 8 || (cmf >> 4) > 7 {
+                return Err(ParseError::BadZlibHeader);
+            }
+
+            if (u16::from(cmf) * 256 + u16::from(flg)) % 31 != 0 {
+                return Err(ParseError::BadZlibHeader);
+            }
+
+            // FDICT: preset dictionaries are not supported.
+            if flg & 0b0010_0000 != 0 {
+                return Err(ParseError::BadZlibHeader);
+            }
+
+            let (deflate_data, adler_bytes) = data[2..].split_at(data.len() - 6);
+            let expected_adler = u32::from_be_bytes(adler_bytes.try_into().unwrap());
+
+            let decompressed = inflate(deflate_data)?;
+
+            if adler32(&decompressed) != expected_adler {
+                return Err(ParseError::AdlerMismatch);
+            }
+
+            Ok(decompressed)
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            // Builds a zlib stream around a single stored (uncompressed)
+            // DEFLATE block, so tests don't need a real Huffman encoder.
+            fn zlib_stored(payload: &[u8]) -> Vec<u8> {
+                let mut out = vec![0x78, 0x01];
+
+                out.push(1); // BFINAL=1, BTYPE=00 (stored), byte-aligned
+                let len = payload.len() as u16;
+                out.extend_from_slice(&len.to_le_bytes());
+                out.extend_from_slice(&(!len).to_le_bytes());
+                out.extend_from_slice(payload);
+
+                out.extend_from_slice(&adler32(payload).to_be_bytes());
+                out
+            }
+
+            #[test]
+            fn stored_block_round_trips() {
+                let payload = b"hello, png world!";
+                let stream = zlib_stored(payload);
+                assert_eq!(zlib_decompress(&stream).unwrap(), payload);
+            }
+
+            // Bit-level writer matching `BitReader`'s convention (bits packed
+            // LSB-first within a byte), used to hand-encode a fixed-Huffman
+            // block below.
+            struct BitWriter {
+                data: Vec<u8>,
+                bit_pos: u32,
+            }
+
+            impl BitWriter {
+                fn new() -> Self {
+                    Self { data: Vec::new(), bit_pos: 0 }
+                }
+
+                fn push_bit(&mut self, bit: u32) {
+                    if self.bit_pos == 0 {
+                        self.data.push(0);
+                    }
+                    let last = self.data.len() - 1;
+                    self.data[last] |= (bit as u8) << self.bit_pos;
+                    self.bit_pos = (self.bit_pos + 1) % 8;
+                }
+
+                // Multi-bit fields (BTYPE, extra bits, ...) are packed
+                // LSB-first, same as `BitReader::read_bits`.
+                fn push_bits(&mut self, value: u32, count: u32) {
+                    for i in 0..count {
+                        self.push_bit((value >> i) & 1);
+                    }
+                }
+
+                // Huffman codes are packed MSB-first, matching
+                // `HuffmanTable::decode`'s bit-by-bit accumulation.
+                fn push_code(&mut self, code: u32, bits: u32) {
+                    for i in (0..bits).rev() {
+                        self.push_bit((code >> i) & 1);
+                    }
+                }
+            }
+
+            #[test]
+            fn back_reference_copies_overlap_from_output() {
+                // Hand-encodes a fixed-Huffman block for the literal 'a'
+                // followed by a length-9/distance-1 back-reference, i.e. an
+                // overlapping copy that must read bytes it just wrote.
+                let mut writer = BitWriter::new();
+                writer.push_bit(1); // BFINAL
+                writer.push_bits(1, 2); // BTYPE = 01 (fixed Huffman)
+                writer.push_code(0x30 + b'a' as u32, 8); // literal 'a'
+                writer.push_code(263 - 256, 7); // length symbol: base 9, no extra bits
+                writer.push_code(0, 5); // distance symbol: base 1, no extra bits
+                writer.push_code(0, 7); // end-of-block (symbol 256)
+
+                let mut stream = vec![0x78, 0x01];
+                stream.extend_from_slice(&writer.data);
+
+                let expected = b"aaaaaaaaaa";
+                stream.extend_from_slice(&adler32(expected).to_be_bytes());
+
+                assert_eq!(zlib_decompress(&stream).unwrap(), expected);
+            }
+
+            #[test]
+            fn rejects_bad_zlib_header() {
+                let mut stream = zlib_stored(b"x");
+                stream[0] = 0x00;
+                assert!(matches!(
+                    zlib_decompress(&stream),
+                    Err(ParseError::BadZlibHeader)
+                ));
+            }
+
+            #[test]
+            fn rejects_preset_dictionary() {
+                let mut stream = zlib_stored(b"x");
+                stream[1] |= 0b0010_0000;
+                assert!(matches!(
+                    zlib_decompress(&stream),
+                    Err(ParseError::BadZlibHeader)
+                ));
+            }
+
+            #[test]
+            fn rejects_adler_mismatch() {
+                let mut stream = zlib_stored(b"hello");
+                let last = stream.len() - 1;
+                stream[last] ^= 0xFF;
+                assert!(matches!(
+                    zlib_decompress(&stream),
+                    Err(ParseError::AdlerMismatch)
+                ));
+            }
+
+            #[test]
+            fn rejects_bad_nlen() {
+                let mut stream = zlib_stored(b"hello");
+                stream[5] ^= 0xFF; // corrupt NLEN's low byte
+                assert!(matches!(zlib_decompress(&stream), Err(ParseError::BadNlen)));
+            }
+        }
+    }
+
+    mod adam7 {
+        // Adam7 interlacing splits the image into 7 reduced images, each
+        // filtered and unfiltered independently, that are then scattered back
+        // into the full grid at their own (x_start, y_start, x_step, y_step).
+        use super::{expand, recon, FilterType, ImageHeader, ParseError, Rgba, TryFromBytes};
+        use std::mem::size_of;
+
+        const PASSES: [(u32, u32, u32, u32); 7] = [
+            (0, 0, 8, 8),
+            (4, 0, 8, 8),
+            (0, 4, 4, 8),
+            (2, 0, 4, 4),
+            (0, 2, 2, 4),
+            (1, 0, 2, 2),
+            (0, 1, 1, 2),
+        ];
+
+        fn reduced_dimension(full: u32, start: u32, step: u32) -> u32 {
+            if full <= start {
+                0
+            } else {
+                (full - start + step - 1) / step
+            }
+        }
+
+        // Walks the seven reduced-image passes, reconstructing each scanline
+        // (at that pass's own bpp/width) and handing it to `on_row` along
+        // with where it belongs in the full image. Shared by both the
+        // straight-to-`Rgba` and the native-format-buffer scatter paths.
+        fn for_each_pass(
+            data: &[u8],
+            header: &ImageHeader,
+            mut on_row: impl FnMut(u32, u32, u32, u32, &[u8]) -> Result<(), ParseError<'static>>,
+        ) -> Result<(), ParseError<'static>> {
+            let width = header.width.get();
+            let height = header.height.get();
+            let distance = header.filter_distance();
+
+            let mut cursor = data;
+
+            for &(x_start, y_start, x_step, y_step) in &PASSES {
+                let pass_width = reduced_dimension(width, x_start, x_step);
+                let pass_height = reduced_dimension(height, y_start, y_step);
+
+                if pass_width == 0 || pass_height == 0 {
+                    continue;
+                }
+
+                let row_bytes = (pass_width as usize * header.bits_per_pixel() as usize).div_ceil(8);
+                let stride = row_bytes + size_of::<FilterType>();
+                let mut above = vec![0u8; row_bytes];
+
+                for row in 0..pass_height {
+                    let line = cursor.get(..stride).ok_or(ParseError::UnexpectedEof)?;
+                    cursor = &cursor[stride..];
+
+                    let (filter, pixel_bytes) =
+                        FilterType::try_ref_from_prefix(line).map_err(|_| ParseError::BadFilterType(line[0]))?;
+                    let mut current = pixel_bytes.to_vec();
+
+                    match filter {
+                        FilterType::Up => recon::up(&mut current, &above),
+                        FilterType::Paeth => recon::paeth(&mut current, &above, distance),
+                        FilterType::Average => recon::average(&mut current, &above, distance),
+                        FilterType::Sub => recon::sub(&mut current, distance),
+                        FilterType::NoFilter => {}
+                    }
+
+                    let y = y_start + row * y_step;
+                    on_row(x_start, y, x_step, pass_width, &current)?;
+
+                    above.copy_from_slice(&current);
+                }
+            }
+
+            Ok(())
+        }
+
+        // Scatters the seven reduced-image passes straight into `pixels`
+        // (already sized width*height), expanding each one to `Rgba` as it's
+        // reconstructed, without an intermediate native-format buffer.
+        pub fn deinterlace_into(
+            data: &[u8],
+            header: &ImageHeader,
+            palette: Option<&[Rgba]>,
+            pixels: &mut [Rgba],
+        ) -> Result<(), ParseError<'static>> {
+            let width = header.width.get();
+
+            for_each_pass(data, header, |x_start, y, x_step, pass_width, row| {
+                for col in 0..pass_width as usize {
+                    let x = x_start + col as u32 * x_step;
+                    pixels[(y * width + x) as usize] = expand::pixel(header, row, palette, col)?;
+                }
+
+                Ok(())
+            })
+        }
+
+        // Like `deinterlace_into`, but scatters reconstructed samples into a
+        // full-width, native-format (not yet expanded to `Rgba`) scanline
+        // buffer, tagged `NoFilter` throughout. Used by `RgbaImage::parse`,
+        // which keeps the lazy, colour-type-agnostic `lines()`/`recon()` API
+        // working the same way for interlaced and non-interlaced sources.
+        pub fn deinterlace_rows(data: &[u8], header: &ImageHeader) -> Result<Box<[u8]>, ParseError<'static>> {
+            let height = header.height.get() as usize;
+            let row_bytes = header.row_bytes();
+            let stride = row_bytes + size_of::<FilterType>();
+            let mut buffer = vec![0u8; stride * height];
+
+            for y in 0..height {
+                buffer[y * stride] = FilterType::NoFilter as u8;
+            }
+
+            for_each_pass(data, header, |x_start, y, x_step, pass_width, row| {
+                let dst_start = y as usize * stride + size_of::<FilterType>();
+                let dst_row = &mut buffer[dst_start..dst_start + row_bytes];
+
+                for col in 0..pass_width as usize {
+                    let x = (x_start + col as u32 * x_step) as usize;
+                    expand::copy_pixel(header, row, col, dst_row, x);
+                }
+
+                Ok(())
+            })?;
+
+            Ok(buffer.into_boxed_slice())
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use super::super::{BitDepth, ColourType, U32};
+
+            #[test]
+            fn reduced_dimension_skips_passes_past_the_edge() {
+                assert_eq!(reduced_dimension(8, 0, 8), 1);
+                assert_eq!(reduced_dimension(8, 4, 8), 1);
+                assert_eq!(reduced_dimension(4, 4, 8), 0); // x_start == width: no columns
+                assert_eq!(reduced_dimension(3, 4, 8), 0); // x_start > width
+                assert_eq!(reduced_dimension(7, 1, 2), 3); // ceil((7-1)/2)
+            }
+
+            // Builds an 8-bit grayscale Adam7 bitstream (seven NoFilter
+            // passes, no IDAT/zlib framing) for an 8x8 image whose sample at
+            // (x, y) is `x * 8 + y`, then checks `deinterlace_into` scatters
+            // every pass back to the right pixel.
+            #[test]
+            fn deinterlace_into_scatters_every_pass() {
+                let width = 8u32;
+                let height = 8u32;
+                let header = ImageHeader {
+                    width: U32::new(width),
+                    height: U32::new(height),
+                    bit_depth: BitDepth::Eight,
+                    colour_type: ColourType::Grayscale,
+                    compression_method: 0,
+                    filter_method: 0,
+                    interlace_method: 1,
+                };
+
+                let sample_at = |x: u32, y: u32| (x * 8 + y) as u8;
+
+                let mut data = Vec::new();
+                for &(x_start, y_start, x_step, y_step) in &PASSES {
+                    let pass_width = reduced_dimension(width, x_start, x_step);
+                    let pass_height = reduced_dimension(height, y_start, y_step);
+
+                    for py in 0..pass_height {
+                        data.push(FilterType::NoFilter as u8);
+                        for px in 0..pass_width {
+                            let x = x_start + px * x_step;
+                            let y = y_start + py * y_step;
+                            data.push(sample_at(x, y));
+                        }
+                    }
+                }
+
+                let mut pixels = vec![Rgba::default(); (width * height) as usize];
+                deinterlace_into(&data, &header, None, &mut pixels).unwrap();
+
+                for y in 0..height {
+                    for x in 0..width {
+                        let v = sample_at(x, y);
+                        let expect = Rgba::new(v, v, v, 255);
+                        assert_eq!(pixels[(y * width + x) as usize], expect, "at ({x}, {y})");
+                    }
+                }
+            }
+
+            // Same bitstream as above, but through `deinterlace_rows`, whose
+            // native-format output should expand to the same pixels as
+            // `deinterlace_into`'s direct-to-`Rgba` path.
+            #[test]
+            fn deinterlace_rows_matches_deinterlace_into() {
+                let width = 8u32;
+                let height = 8u32;
+                let header = ImageHeader {
+                    width: U32::new(width),
+                    height: U32::new(height),
+                    bit_depth: BitDepth::Eight,
+                    colour_type: ColourType::Grayscale,
+                    compression_method: 0,
+                    filter_method: 0,
+                    interlace_method: 1,
+                };
+
+                let sample_at = |x: u32, y: u32| (x * 8 + y) as u8;
+
+                let mut data = Vec::new();
+                for &(x_start, y_start, x_step, y_step) in &PASSES {
+                    let pass_width = reduced_dimension(width, x_start, x_step);
+                    let pass_height = reduced_dimension(height, y_start, y_step);
+
+                    for py in 0..pass_height {
+                        data.push(FilterType::NoFilter as u8);
+                        for px in 0..pass_width {
+                            let x = x_start + px * x_step;
+                            let y = y_start + py * y_step;
+                            data.push(sample_at(x, y));
+                        }
+                    }
+                }
+
+                let mut via_into = vec![Rgba::default(); (width * height) as usize];
+                deinterlace_into(&data, &header, None, &mut via_into).unwrap();
+
+                let rows = deinterlace_rows(&data, &header).unwrap();
+                let row_bytes = header.row_bytes();
+                let stride = row_bytes + size_of::<FilterType>();
+
+                for y in 0..height as usize {
+                    let row = &rows[y * stride + size_of::<FilterType>()..y * stride + stride];
+                    for x in 0..width as usize {
+                        let got = expand::pixel(&header, row, None, x).unwrap();
+                        assert_eq!(got, via_into[y * width as usize + x], "at ({x}, {y})");
+                    }
+                }
+            }
+        }
     }
 
     mod recon {
         // CBA to SIMD
-        use super::Rgba;
+        //
+        // Operates on raw scanline bytes rather than whole pixels: `bpp` is
+        // the byte distance back to the "left" sample, i.e.
+        // `ImageHeader::filter_distance()` (bits-per-pixel rounded up to a
+        // byte, minimum 1), which lets the same code reconstruct any colour
+        // type/bit depth combination instead of only 4-byte Rgba.
 
         pub fn paeth_predictor(left: u8, above: u8, upper_left: u8) -> u8 {
             // To prevent overflows
@@ -258,7 +1722,7 @@ mod png {
             if predictor_left <= predictor_above && predictor_left <= predictor_upper_left {
                 return left;
             }
-            
+
             if predictor_above <= predictor_upper_left {
                 return above;
             }
@@ -266,50 +1730,32 @@ mod png {
             upper_left
         }
 
-        pub fn up(current_line: &mut [Rgba], above: &[Rgba]) {
-            for (pixel, &above_pixel) in current_line.iter_mut().zip(above) {
-                *pixel += above_pixel;
+        pub fn up(current_line: &mut [u8], above: &[u8]) {
+            for (byte, &above_byte) in current_line.iter_mut().zip(above) {
+                *byte = byte.wrapping_add(above_byte);
             }
         }
 
-        pub fn sub(current_line: &mut [Rgba]) {
-            let mut left_pixel = Rgba::default();
-
-            for pixel in current_line {
-                *pixel += left_pixel;
-                left_pixel = *pixel;
+        pub fn sub(current_line: &mut [u8], bpp: usize) {
+            for i in bpp..current_line.len() {
+                current_line[i] = current_line[i].wrapping_add(current_line[i - bpp]);
             }
         }
 
-        pub fn average(current_line: &mut [Rgba], above: &[Rgba]) {
-            let mut left_pixel = Rgba::default();
-
-            for (pixel, &above_pixel) in current_line.iter_mut().zip(above) {
-                *pixel += Rgba::new(
-                    ((left_pixel.r as u16 + above_pixel.r as u16) >> 1) as u8,
-                    ((left_pixel.g as u16 + above_pixel.g as u16) >> 1) as u8,
-                    ((left_pixel.b as u16 + above_pixel.b as u16) >> 1) as u8,
-                    ((left_pixel.a as u16 + above_pixel.a as u16) >> 1) as u8,
-                );
-
-                left_pixel = *pixel;
+        pub fn average(current_line: &mut [u8], above: &[u8], bpp: usize) {
+            for i in 0..current_line.len() {
+                let left = if i >= bpp { current_line[i - bpp] as u16 } else { 0 };
+                let above_byte = above[i] as u16;
+                current_line[i] = current_line[i].wrapping_add(((left + above_byte) >> 1) as u8);
             }
         }
 
-        pub fn paeth(current_line: &mut [Rgba], above: &[Rgba]) {
-            let mut left_pixel = Rgba::default();
-            let mut upper_left_pixel = Rgba::default();
-
-            for (pixel, &above_pixel) in current_line.iter_mut().zip(above) {
-                *pixel += Rgba::new(
-                    paeth_predictor(left_pixel.r, above_pixel.r, upper_left_pixel.r),
-                    paeth_predictor(left_pixel.g, above_pixel.g, upper_left_pixel.g),
-                    paeth_predictor(left_pixel.b, above_pixel.b, upper_left_pixel.b),
-                    paeth_predictor(left_pixel.a, above_pixel.a, upper_left_pixel.a),
-                );
-
-                left_pixel = *pixel;
-                upper_left_pixel = above_pixel;
+        pub fn paeth(current_line: &mut [u8], above: &[u8], bpp: usize) {
+            for i in 0..current_line.len() {
+                let left = if i >= bpp { current_line[i - bpp] } else { 0 };
+                let upper_left = if i >= bpp { above[i - bpp] } else { 0 };
+                current_line[i] =
+                    current_line[i].wrapping_add(paeth_predictor(left, above[i], upper_left));
             }
         }
 
@@ -317,27 +1763,18 @@ mod png {
         mod tests {
             use super::*;
 
+            const BPP: usize = 4;
+
             #[test]
             fn test_up() {
-                let mut row = [
-                    Rgba::new(1, 0, 0, 0),
-                    Rgba::new(1, 3, 2, 0),
-                    Rgba::new(0, 4, 3, 0),
-                    Rgba::new(5, 0, 1, 0),
-                ];
+                let mut row = [1, 0, 0, 0, 1, 3, 2, 0, 0, 4, 3, 0, 5, 0, 1, 0];
 
-                const PREVIOUS: [Rgba; 4] = [
-                    Rgba::new(128, 60, 40, 10),
-                    Rgba::new(130, 64, 40, 10),
-                    Rgba::new(128, 61, 40, 10),
-                    Rgba::new(130, 46, 20, 10),
+                const PREVIOUS: [u8; 16] = [
+                    128, 60, 40, 10, 130, 64, 40, 10, 128, 61, 40, 10, 130, 46, 20, 10,
                 ];
 
-                const RESULT: [Rgba; 4] = [
-                    Rgba::new(129, 60, 40, 10),
-                    Rgba::new(131, 67, 42, 10),
-                    Rgba::new(128, 65, 43, 10),
-                    Rgba::new(135, 46, 21, 10),
+                const RESULT: [u8; 16] = [
+                    129, 60, 40, 10, 131, 67, 42, 10, 128, 65, 43, 10, 135, 46, 21, 10,
                 ];
 
                 up(&mut row, &PREVIOUS);
@@ -346,75 +1783,45 @@ mod png {
 
             #[test]
             fn test_average() {
-                let mut row = [
-                    Rgba::new(65, 30, 20, 5),
-                    Rgba::new(2, 5, 2, 5),
-                    Rgba::new(255, 1, 2, 5),
-                    Rgba::new(6, 247, 10, 5),
-                ];
+                let mut row = [65, 30, 20, 5, 2, 5, 2, 5, 255, 1, 2, 5, 6, 247, 10, 5];
 
-                const PREVIOUS: [Rgba; 4] = [
-                    Rgba::new(128, 60, 40, 10),
-                    Rgba::new(130, 64, 40, 10),
-                    Rgba::new(128, 61, 40, 10),
-                    Rgba::new(130, 46, 20, 10),
+                const PREVIOUS: [u8; 16] = [
+                    128, 60, 40, 10, 130, 64, 40, 10, 128, 61, 40, 10, 130, 46, 20, 10,
                 ];
 
-                const RESULT: [Rgba; 4] = [
-                    Rgba::new(129, 60, 40, 10),
-                    Rgba::new(131, 67, 42, 15),
-                    Rgba::new(128, 65, 43, 17),
-                    Rgba::new(135, 46, 41, 18),
+                const RESULT: [u8; 16] = [
+                    129, 60, 40, 10, 131, 67, 42, 15, 128, 65, 43, 17, 135, 46, 41, 18,
                 ];
 
-                average(&mut row, &PREVIOUS);
+                average(&mut row, &PREVIOUS, BPP);
                 assert_eq!(row, RESULT);
             }
 
             #[test]
             fn test_paeth() {
-                let mut row = [
-                    Rgba::new(1, 0, 5, 0),
-                    Rgba::new(1, 4, 255, 0),
-                    Rgba::new(255, 252, 3, 0),
-                    Rgba::new(4, 2, 3, 0),
-                ];
+                let mut row = [1, 0, 5, 0, 1, 4, 255, 0, 255, 252, 3, 0, 4, 2, 3, 0];
 
-                const PREVIOUS: [Rgba; 4] = [
-                    Rgba::new(128, 60, 90, 10),
-                    Rgba::new(129, 61, 90, 10),
-                    Rgba::new(128, 60, 91, 10),
-                    Rgba::new(130, 65, 97, 10),
+                const PREVIOUS: [u8; 16] = [
+                    128, 60, 90, 10, 129, 61, 90, 10, 128, 60, 91, 10, 130, 65, 97, 10,
                 ];
 
-                const RESULT: [Rgba; 4] = [
-                    Rgba::new(129, 60, 95, 10),
-                    Rgba::new(130, 65, 94, 10),
-                    Rgba::new(128, 61, 97, 10),
-                    Rgba::new(134, 67, 100, 10),
+                const RESULT: [u8; 16] = [
+                    129, 60, 95, 10, 130, 65, 94, 10, 128, 61, 97, 10, 134, 67, 100, 10,
                 ];
 
-                paeth(&mut row, &PREVIOUS);
+                paeth(&mut row, &PREVIOUS, BPP);
                 assert_eq!(row, RESULT);
             }
 
             #[test]
             fn test_sub() {
-                let mut row = [
-                    Rgba::new(128, 60, 40, 10),
-                    Rgba::new(2, 4, 0, 0),
-                    Rgba::new(254, 253, 0, 0),
-                    Rgba::new(2, 241, 236, 0),
-                ];
+                let mut row = [128, 60, 40, 10, 2, 4, 0, 0, 254, 253, 0, 0, 2, 241, 236, 0];
 
-                const RESULT: [Rgba; 4] = [
-                    Rgba::new(128, 60, 40, 10),
-                    Rgba::new(130, 64, 40, 10),
-                    Rgba::new(128, 61, 40, 10),
-                    Rgba::new(130, 46, 20, 10),
+                const RESULT: [u8; 16] = [
+                    128, 60, 40, 10, 130, 64, 40, 10, 128, 61, 40, 10, 130, 46, 20, 10,
                 ];
 
-                sub(&mut row);
+                sub(&mut row, BPP);
                 assert_eq!(row, RESULT);
             }
         }